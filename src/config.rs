@@ -0,0 +1,75 @@
+use serde::Deserialize;
+
+/// On-disk TOML profile loaded via `--config`. Every field is optional: file
+/// values are defaults that explicit CLI flags override, so a profile only
+/// needs to set what it wants to change.
+#[derive(Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub scan: ScanConfig,
+    #[serde(default)]
+    pub discord: DiscordConfig,
+    #[serde(default)]
+    pub telegram: TelegramConfig,
+    #[serde(default)]
+    pub json_webhook: JsonWebhookConfig,
+    #[serde(default)]
+    pub irc: IrcConfig,
+}
+
+#[derive(Default, Deserialize)]
+pub struct ScanConfig {
+    pub concurrent: Option<usize>,
+    pub user_agent: Option<String>,
+    pub min_size: Option<u64>,
+    pub verify_placeholder: Option<bool>,
+    pub state_file: Option<String>,
+    pub extensions: Option<String>,
+    pub code_length: Option<usize>,
+    pub scan_albums: Option<bool>,
+    pub report_size: Option<bool>,
+    pub export_file: Option<String>,
+    pub download_dir: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+pub struct DiscordConfig {
+    pub webhook_id: Option<u64>,
+    pub webhook_token: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+pub struct TelegramConfig {
+    pub channel: Option<String>,
+    pub token: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+pub struct JsonWebhookConfig {
+    pub endpoint: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+pub struct IrcConfig {
+    pub server: Option<String>,
+    pub port: Option<u16>,
+    pub channel: Option<String>,
+    pub nick: Option<String>,
+}
+
+pub fn load(path: &str) -> Config {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("warning: failed to read config file {}: {}", path, e);
+            return Config::default();
+        }
+    };
+    match toml::from_str(&contents) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("warning: failed to parse config file {}: {}", path, e);
+            Config::default()
+        }
+    }
+}
@@ -1,32 +1,71 @@
+use bloomfilter::Bloom;
 use clap::{App, Arg};
-use futures::{stream, Future, Stream};
+use futures::future::Either;
+use futures::{stream, Async, Future, Poll, Stream};
 use hyper::{Body, Client, Request, StatusCode, Uri, header};
 use hyper_tls::HttpsConnector;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
-use std::fs::OpenOptions;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::hash::{Hash, Hasher};
 use std::io::prelude::*;
+use std::io::BufReader;
 use std::iter;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::mpsc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio;
+use tokio::timer::Delay;
+use tokio_sync::semaphore::{Permit, Semaphore};
+
+mod config;
+mod sinks;
+
+use sinks::{DiscordSink, DiskSink, FileSink, FoundImage, IrcSink, JsonWebhookSink, OutputSink, TelegramSink};
+
+const PLACEHOLDER_FINGERPRINT_BYTES: usize = 512;
 
 const BASE_URL: &str = "https://i.imgur.com/";
 
+// Album/gallery pages live on a different host than direct images and use
+// Imgur's shorter 5-char codes.
+const ALBUM_BASE_URL: &str = "https://imgur.com/a/";
+const ALBUM_CODE_LENGTH: usize = 5;
+const ALBUM_CONCURRENCY: usize = 8;
+
+// Sized for tens of millions of attempted codes at ~1% false-positive rate,
+// so a long-running scan cheaply skips codes it has already issued a request for.
+const EXPECTED_ATTEMPTED_CODES: usize = 50_000_000;
+const ATTEMPTED_FALSE_POSITIVE_RATE: f64 = 0.01;
+
+const INITIAL_BACKOFF_MS: usize = 500;
+const MAX_BACKOFF_MS: usize = 60_000;
+const CLEAN_RAMP_WINDOW: usize = 200;
+
 struct UriGenerator {
     base_url: String,
-    extension: String,
+    extensions: Vec<String>,
+    code_length: usize,
+    attempted: Bloom<String>,
+    pending: Vec<Uri>,
 }
 
 impl UriGenerator {
-    fn new(base_url: String, extension: String) -> Self {
+    fn new(base_url: String, extensions: Vec<String>, code_length: usize) -> Self {
         Self {
             base_url,
-            extension,
+            extensions,
+            code_length,
+            attempted: Bloom::new_for_fp_rate(
+                EXPECTED_ATTEMPTED_CODES,
+                ATTEMPTED_FALSE_POSITIVE_RATE,
+            ),
+            pending: Vec::new(),
         }
     }
 }
@@ -35,76 +74,249 @@ impl Iterator for UriGenerator {
     type Item = Uri;
 
     fn next(&mut self) -> Option<Uri> {
-        Some(
-            format!(
-                "{}{}{}",
-                self.base_url,
-                iter::repeat(())
-                    .map(|()| thread_rng().sample(Alphanumeric))
-                    .take(7)
-                    .collect::<String>(),
-                self.extension
-            )
-                .parse()
-                .unwrap(),
-        )
+        if let Some(uri) = self.pending.pop() {
+            return Some(uri);
+        }
+
+        loop {
+            let code = iter::repeat(())
+                .map(|()| thread_rng().sample(Alphanumeric))
+                .take(self.code_length)
+                .collect::<String>();
+
+            if self.attempted.check(&code) {
+                continue;
+            }
+
+            self.attempted.set(&code);
+
+            let base_url = &self.base_url;
+            self.pending = self
+                .extensions
+                .iter()
+                .map(|extension| {
+                    format!("{}{}.{}", base_url, code, extension)
+                        .parse()
+                        .unwrap()
+                })
+                .collect();
+
+            return self.pending.pop();
+        }
+    }
+}
+
+// Shared across loop restarts, like `found_codes`, so a dropped connection
+// doesn't reset the Bloom filter and re-scan already-attempted codes.
+struct SharedUriGenerator(Arc<Mutex<UriGenerator>>);
+
+impl Iterator for SharedUriGenerator {
+    type Item = Uri;
+
+    fn next(&mut self) -> Option<Uri> {
+        self.0.lock().unwrap().next()
     }
 }
 
-fn stream_to_telegram(channel: String, token: String, rx: mpsc::Receiver<String>) {
+// Album/gallery codes are a separate namespace from direct-image codes (own
+// host, own Bloom filter) and carry no extension, so this is kept as its own
+// generator rather than folded into `UriGenerator`.
+struct AlbumUriGenerator {
+    attempted: Bloom<String>,
+}
+
+impl AlbumUriGenerator {
+    fn new() -> Self {
+        Self {
+            attempted: Bloom::new_for_fp_rate(
+                EXPECTED_ATTEMPTED_CODES,
+                ATTEMPTED_FALSE_POSITIVE_RATE,
+            ),
+        }
+    }
+}
+
+impl Iterator for AlbumUriGenerator {
+    type Item = Uri;
+
+    fn next(&mut self) -> Option<Uri> {
+        loop {
+            let code = iter::repeat(())
+                .map(|()| thread_rng().sample(Alphanumeric))
+                .take(ALBUM_CODE_LENGTH)
+                .collect::<String>();
+
+            if self.attempted.check(&code) {
+                continue;
+            }
+
+            self.attempted.set(&code);
+
+            return Some(format!("{}{}", ALBUM_BASE_URL, code).parse().unwrap());
+        }
+    }
+}
+
+struct SharedAlbumUriGenerator(Arc<Mutex<AlbumUriGenerator>>);
+
+impl Iterator for SharedAlbumUriGenerator {
+    type Item = Uri;
+
+    fn next(&mut self) -> Option<Uri> {
+        self.0.lock().unwrap().next()
+    }
+}
+
+fn fingerprint(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes[..bytes.len().min(PLACEHOLDER_FINGERPRINT_BYTES)].hash(&mut hasher);
+    hasher.finish()
+}
+
+// Random 7-char codes are almost never valid images, so a handful of GETs
+// taken right at startup double as a reliable sample of Imgur's "removed"
+// placeholder response, without hardcoding its bytes.
+fn capture_placeholder_fingerprints(
+    user_agent: &str,
+    extensions: Vec<String>,
+    code_length: usize,
+) -> HashSet<u64> {
     let https = HttpsConnector::new(4).expect("TLS initialization failed");
     let client = Client::builder().build::<_, hyper::Body>(https);
 
     let mut runtime = tokio::runtime::Runtime::new().expect("Unable to create a runtime");
 
-    for image_url in rx {
+    let images = UriGenerator::new(BASE_URL.to_string(), extensions, code_length);
+    let mut fingerprints = HashSet::new();
+
+    for uri in images.take(3) {
+        let mut request = Request::get(uri);
+        request.header("User-Agent", user_agent);
+
         let work = client
-            .get(
-                format!(
-                    "https://api.telegram.org/bot{}/sendPhoto?chat_id={}&photo={}",
-                    token, channel, image_url
-                )
-                    .parse()
-                    .unwrap(),
-            )
+            .request(request.body(Body::empty()).unwrap())
             .and_then(|res| res.into_body().concat2())
-            .map(|_body| {})
-            .map_err(|_| {});
+            .map_err(|_| ());
+
+        if let Ok(chunk) = runtime.block_on(work) {
+            fingerprints.insert(fingerprint(&chunk));
+        }
+    }
 
-        runtime.block_on(work).is_err();
+    fingerprints
+}
+
+// Album/gallery pages are served as an HTML shell rather than a sized
+// image, so there's no Content-Length-based placeholder check to reuse;
+// a probe is reported as a find purely on a 200 status. Runs as its own
+// loop with plain, non-adaptive concurrency (no 429 backoff/ramp), since
+// album codes are a separate namespace scanned alongside, not instead of,
+// direct images. Found album codes are deduplicated in memory only; they are
+// not written to the `--state` file, so a restart can report them again.
+fn scan_album_codes(user_agent: String, output_sinks: Arc<Vec<Box<dyn OutputSink>>>, total_found: Arc<AtomicUsize>) {
+    let albums = Arc::new(Mutex::new(AlbumUriGenerator::new()));
+    let found_albums = Arc::new(Mutex::new(HashSet::new()));
+
+    loop {
+        let https = HttpsConnector::new(4).expect("TLS initialization failed");
+        let client = Client::builder().build::<_, hyper::Body>(https);
+
+        let albums = SharedAlbumUriGenerator(albums.clone());
+        let user_agent = user_agent.clone();
+        let output_sinks = output_sinks.clone();
+        let found_albums = found_albums.clone();
+        let total_found = total_found.clone();
+
+        let work = stream::iter_ok(albums)
+            .map(move |uri| {
+                let mut request = Request::get(uri.clone());
+
+                request.header("User-Agent", user_agent.clone());
+
+                client
+                    .request(request.body(Body::empty()).unwrap())
+                    .map(move |res| (res, uri))
+            })
+            .buffer_unordered(ALBUM_CONCURRENCY)
+            .and_then(move |(res, uri)| {
+                if res.status() == StatusCode::OK {
+                    let album_url = format!(
+                        "{}://{}{}",
+                        uri.scheme_str().unwrap(),
+                        uri.authority_part().unwrap(),
+                        uri.path_and_query().unwrap()
+                    );
+                    let code = code_from_url(&album_url);
+
+                    if found_albums.lock().unwrap().insert(code.clone()) {
+                        total_found.fetch_add(1, Ordering::SeqCst);
+
+                        println!("\x1B[Kfound album at {}", album_url);
+
+                        let found = FoundImage {
+                            code,
+                            url: album_url,
+                            size: None,
+                        };
+
+                        for sink in output_sinks.iter() {
+                            sink.deliver(&found);
+                        }
+                    }
+                }
+
+                res.into_body().concat2()
+            })
+            .for_each(|_body| Ok(()))
+            .map_err(|e| eprintln!("{}", e));
+
+        tokio::run(work);
     }
 }
 
-fn stream_to_webhook(id: u64, token: String, rx: mpsc::Receiver<String>) {
-    use serenity::http;
-    use serenity::model::channel::Embed;
+fn code_from_url(image_url: &str) -> String {
+    let file_name = image_url.rsplit('/').next().unwrap_or(image_url);
+    file_name.split('.').next().unwrap_or(file_name).to_string()
+}
 
-    let webhook = http::get_webhook_with_token(id, &token).expect("valid webhook");
+fn load_found_codes(path: &str) -> HashSet<String> {
+    let mut codes = HashSet::new();
 
-    for image_url in rx {
-        let resources = Embed::fake(|e| e.image(image_url));
-        let _ = webhook.execute(false, |w| w.embeds(vec![resources]));
+    if let Ok(file) = File::open(path) {
+        for line in BufReader::new(file).lines() {
+            if let Ok(code) = line {
+                if !code.is_empty() {
+                    codes.insert(code);
+                }
+            }
+        }
     }
+
+    codes
 }
 
-fn stream_to_file(path: String, rx: mpsc::Receiver<String>, rx_size: Option<mpsc::Receiver<u64>>) {
+fn stream_found_codes(path: String, rx: mpsc::Receiver<String>) {
     let mut file = OpenOptions::new()
         .append(true)
         .create(true)
         .open(path)
         .unwrap();
 
-    if let Some(rx_size) = rx_size {
-        for image_url in rx {
-            if let Ok(size) = rx_size.try_recv() {
-                file.write_all(format!("{} {}\n", image_url, size).as_bytes())
-                    .unwrap();
-            }
+    let flush_interval = Duration::from_secs(5);
+    let mut pending = Vec::new();
+
+    loop {
+        match rx.recv_timeout(flush_interval) {
+            Ok(code) => pending.push(code),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
         }
-    } else {
-        for image_url in rx {
-            file.write_all(format!("{}\n", image_url).as_bytes())
-                .unwrap();
+
+        if !pending.is_empty() {
+            for code in pending.drain(..) {
+                file.write_all(format!("{}\n", code).as_bytes()).unwrap();
+            }
+            file.flush().unwrap();
         }
     }
 }
@@ -114,6 +326,7 @@ fn print_statistics(
     request_per_second: Arc<AtomicUsize>,
     total_requests: Arc<AtomicUsize>,
     total_found: Arc<AtomicUsize>,
+    effective_permits: Arc<AtomicUsize>,
 ) {
     let mut elapsed_seconds = 0;
     let mut elapsed_milliseconds = 0;
@@ -122,12 +335,13 @@ fn print_statistics(
     let mut cached_found_per_minute = 0;
     loop {
         print!(
-            "{} req / sec - {} found / min - uptime {}s - total reqs {} - total found {}\r",
+            "{} req / sec - {} found / min - uptime {}s - total reqs {} - total found {} - {} concurrent permits\r",
             cached_found_per_seconds,
             cached_found_per_minute,
             elapsed_seconds,
             total_requests.load(Ordering::SeqCst),
-            total_found.load(Ordering::SeqCst)
+            total_found.load(Ordering::SeqCst),
+            effective_permits.load(Ordering::SeqCst)
         );
 
         std::io::stdout().flush().unwrap();
@@ -149,6 +363,72 @@ fn print_statistics(
     }
 }
 
+// Waits for a permit from a shared `Semaphore`, so the number of requests
+// actually in flight is bounded by the semaphore's current capacity rather
+// than by a fixed `buffer_unordered` width.
+struct AcquirePermit {
+    semaphore: Arc<Semaphore>,
+    permit: Permit,
+}
+
+impl AcquirePermit {
+    fn new(semaphore: Arc<Semaphore>) -> Self {
+        Self {
+            semaphore,
+            permit: Permit::new(),
+        }
+    }
+}
+
+impl Future for AcquirePermit {
+    type Item = (Arc<Semaphore>, Permit);
+    type Error = hyper::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.permit.poll_acquire(&self.semaphore) {
+            Ok(Async::Ready(())) => {
+                let permit = std::mem::replace(&mut self.permit, Permit::new());
+                Ok(Async::Ready((self.semaphore.clone(), permit)))
+            }
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(_) => unreachable!("semaphore is never closed"),
+        }
+    }
+}
+
+// Shrinks `semaphore`'s real capacity by `by` permits so concurrency
+// actually drops during backoff, instead of only delaying issuance.
+// Acquiring then forgetting a permit is the documented way to do this with
+// `tokio_sync::semaphore::Semaphore`, which has no direct "set capacity"
+// API; each forgotten permit waits for one to free up before disappearing,
+// so the shrink completes gradually as in-flight requests finish.
+fn shrink_semaphore(semaphore: Arc<Semaphore>, by: usize) {
+    for _ in 0..by {
+        let semaphore = semaphore.clone();
+        tokio::spawn(futures::future::poll_fn(move || {
+            let mut permit = Permit::new();
+            match permit.poll_acquire(&semaphore) {
+                Ok(Async::Ready(())) => {
+                    permit.forget();
+                    Ok(Async::Ready(()))
+                }
+                Ok(Async::NotReady) => Ok(Async::NotReady),
+                Err(_) => Err(()),
+            }
+        }));
+    }
+}
+
+// CLI flags win when the user actually typed them; otherwise a `--config`
+// TOML value is used, falling back to whatever clap's own default supplies.
+fn resolved_str(matches: &clap::ArgMatches, name: &str, from_config: Option<String>) -> Option<String> {
+    if matches.occurrences_of(name) > 0 {
+        matches.value_of(name).map(String::from)
+    } else {
+        from_config.or_else(|| matches.value_of(name).map(String::from))
+    }
+}
+
 fn main() {
     let matches = App::new("imgur-enumerator")
         .version("0.1")
@@ -212,52 +492,222 @@ fn main() {
                 .takes_value(false)
                 .help("Report the image size when exporting to a file")
         )
+        .arg(
+            Arg::with_name("download_dir")
+                .long("download")
+                .short("d")
+                .takes_value(true)
+                .help("Directory where found images will be downloaded to")
+        )
+        .arg(
+            Arg::with_name("min_size")
+                .long("min-size")
+                .takes_value(true)
+                .default_value("1024")
+                .help("Reject OK responses with a Content-Length at or below this many bytes (filters Imgur's placeholder image)")
+        )
+        .arg(
+            Arg::with_name("verify_placeholder")
+                .long("verify-placeholder")
+                .takes_value(false)
+                .help("Re-fetch surviving candidates and compare a hash of the body against placeholder fingerprints sampled at startup")
+        )
+        .arg(
+            Arg::with_name("state_file")
+                .long("state")
+                .takes_value(true)
+                .default_value("found.txt")
+                .help("Path to a file of previously found image codes, loaded at startup and appended to as new image codes are found")
+        )
+        .arg(
+            Arg::with_name("json_webhook")
+                .long("json-webhook")
+                .takes_value(true)
+                .help("Endpoint to POST {code,url,size} JSON to for each found image")
+        )
+        .arg(
+            Arg::with_name("irc_server")
+                .long("irc-server")
+                .takes_value(true)
+                .help("IRC server hostname to announce found images on")
+        )
+        .arg(
+            Arg::with_name("irc_port")
+                .long("irc-port")
+                .takes_value(true)
+                .default_value("6697")
+                .help("IRC server port")
+        )
+        .arg(
+            Arg::with_name("irc_channel")
+                .long("irc-channel")
+                .takes_value(true)
+                .help("IRC channel to announce found images in")
+        )
+        .arg(
+            Arg::with_name("irc_nick")
+                .long("irc-nick")
+                .takes_value(true)
+                .default_value("imgur-enumerator")
+                .help("Nickname to use when connecting to IRC")
+        )
+        .arg(
+            Arg::with_name("extensions")
+                .long("ext")
+                .takes_value(true)
+                .default_value("png")
+                .help("Comma-separated list of extensions to probe for each generated code, e.g. png,jpg,gif,mp4")
+        )
+        .arg(
+            Arg::with_name("code_length")
+                .long("code-length")
+                .takes_value(true)
+                .default_value("7")
+                .help("Length of the random code to generate against the direct-image host, e.g. 7 for Imgur's image codes")
+        )
+        .arg(
+            Arg::with_name("scan_albums")
+                .long("scan-albums")
+                .takes_value(false)
+                .help("Also scan Imgur's 5-char album/gallery code space (imgur.com/a/<code>), reported to the same output sinks as found images")
+        )
+        .arg(
+            Arg::with_name("config_file")
+                .long("config")
+                .takes_value(true)
+                .help("Path to a TOML config file providing defaults; explicit CLI flags take priority over it")
+        )
         .get_matches();
 
-    let n_concurrent = matches.value_of("concurrent").unwrap().parse().unwrap();
-    let user_agent = matches.value_of("user_agent").unwrap().to_string();
+    let config = matches
+        .value_of("config_file")
+        .map(config::load)
+        .unwrap_or_default();
 
-    let (tx, rx) = mpsc::channel::<String>();
-    let (tx_hook, rx_hook) = mpsc::channel::<String>();
-    let (tx_tg, rx_tg) = mpsc::channel::<String>();
-    let (tx_size, rx_size) = mpsc::channel::<u64>();
+    let n_concurrent: usize = resolved_str(&matches, "concurrent", config.scan.concurrent.map(|v| v.to_string()))
+        .unwrap()
+        .parse()
+        .unwrap();
+    let user_agent = resolved_str(&matches, "user_agent", config.scan.user_agent.clone()).unwrap();
+    let min_size: u64 = resolved_str(&matches, "min_size", config.scan.min_size.map(|v| v.to_string()))
+        .unwrap()
+        .parse()
+        .unwrap();
+    let verify_placeholder =
+        matches.is_present("verify_placeholder") || config.scan.verify_placeholder.unwrap_or(false);
+    let state_path = resolved_str(&matches, "state_file", config.scan.state_file.clone()).unwrap();
+    let extensions: Vec<String> = resolved_str(&matches, "extensions", config.scan.extensions.clone())
+        .unwrap()
+        .split(',')
+        .map(|ext| ext.trim().to_string())
+        .collect();
+    let code_length: usize = resolved_str(&matches, "code_length", config.scan.code_length.map(|v| v.to_string()))
+        .unwrap()
+        .parse()
+        .unwrap();
+    let scan_albums = matches.is_present("scan_albums") || config.scan.scan_albums.unwrap_or(false);
+
+    let known_fingerprints = Arc::new(Mutex::new(if verify_placeholder {
+        capture_placeholder_fingerprints(&user_agent, extensions.clone(), code_length)
+    } else {
+        HashSet::new()
+    }));
+
+    let found_codes = Arc::new(Mutex::new(load_found_codes(&state_path)));
+    println!(
+        "Loaded {} previously found codes from {}.",
+        found_codes.lock().unwrap().len(),
+        state_path
+    );
+
+    let (tx_state, rx_state) = mpsc::channel::<String>();
+    thread::spawn(move || stream_found_codes(state_path, rx_state));
 
-    if matches.is_present("webhook_id") && matches.is_present("webhook_token") {
-        let id = matches.value_of("webhook_id").unwrap().parse().unwrap();
-        let token: String = matches.value_of("webhook_token").unwrap().to_string();
+    let mut output_sinks: Vec<Box<dyn OutputSink>> = Vec::new();
 
-        thread::spawn(move || stream_to_webhook(id, token, rx_hook));
+    let webhook_id = resolved_str(&matches, "webhook_id", config.discord.webhook_id.map(|v| v.to_string()));
+    let webhook_token = resolved_str(&matches, "webhook_token", config.discord.webhook_token.clone());
+
+    if let (Some(id), Some(token)) = (webhook_id, webhook_token) {
+        output_sinks.push(Box::new(DiscordSink::new(id.parse().unwrap(), token)));
     }
 
-    if matches.is_present("export_file") {
-        let export_path: String = matches.value_of("export_file").unwrap().to_string();
+    let export_file = resolved_str(&matches, "export_file", config.scan.export_file.clone());
 
-        if matches.is_present("report_size") {
-            thread::spawn(move || stream_to_file(export_path, rx, Some(rx_size)));
-        } else {
-            thread::spawn(move || stream_to_file(export_path, rx, None));
-        }
+    if let Some(export_path) = export_file {
+        let report_size = matches.is_present("report_size") || config.scan.report_size.unwrap_or(false);
+
+        output_sinks.push(Box::new(FileSink::new(export_path, report_size)));
     }
 
-    if matches.is_present("tg_channel") && matches.is_present("tg_token") {
-        let channel = matches.value_of("tg_channel").unwrap().to_string();
-        let token: String = matches.value_of("tg_token").unwrap().to_string();
+    let tg_channel = resolved_str(&matches, "tg_channel", config.telegram.channel.clone());
+    let tg_token = resolved_str(&matches, "tg_token", config.telegram.token.clone());
 
-        thread::spawn(move || stream_to_telegram(channel, token, rx_tg));
+    if let (Some(channel), Some(token)) = (tg_channel, tg_token) {
+        output_sinks.push(Box::new(TelegramSink::new(channel, token)));
     }
 
+    let download_dir = resolved_str(&matches, "download_dir", config.scan.download_dir.clone());
+
+    if let Some(download_dir) = download_dir {
+        output_sinks.push(Box::new(DiskSink::new(download_dir, n_concurrent)));
+    }
+
+    let json_webhook = resolved_str(&matches, "json_webhook", config.json_webhook.endpoint.clone());
+
+    if let Some(endpoint) = json_webhook {
+        output_sinks.push(Box::new(JsonWebhookSink::new(endpoint)));
+    }
+
+    let irc_server = resolved_str(&matches, "irc_server", config.irc.server.clone());
+    let irc_channel = resolved_str(&matches, "irc_channel", config.irc.channel.clone());
+
+    if let (Some(server), Some(channel)) = (irc_server, irc_channel) {
+        let port: u16 = resolved_str(&matches, "irc_port", config.irc.port.map(|v| v.to_string()))
+            .unwrap()
+            .parse()
+            .unwrap();
+        let nickname = resolved_str(&matches, "irc_nick", config.irc.nick.clone()).unwrap();
+
+        output_sinks.push(Box::new(IrcSink::new(server, port, channel, nickname)));
+    }
+
+    let output_sinks = Arc::new(output_sinks);
+
     let request_per_second = Arc::new(AtomicUsize::new(0));
     let found_per_minute = Arc::new(AtomicUsize::new(0));
 
     let total_requests = Arc::new(AtomicUsize::new(0));
     let total_found = Arc::new(AtomicUsize::new(0));
 
+    if scan_albums {
+        let user_agent = user_agent.clone();
+        let output_sinks = output_sinks.clone();
+        let total_found = total_found.clone();
+
+        thread::spawn(move || scan_album_codes(user_agent, output_sinks, total_found));
+    }
+
+    // Adaptive rate limiting: `effective_permits` is the concurrency budget
+    // actually in use (throttled down from `n_concurrent` on 429s and ramped
+    // back up after a clean streak), mirrored onto `semaphore`'s real
+    // capacity so requests are actually gated rather than merely delayed.
+    // `backoff_ms` is the current exponential backoff delay, and `resume_at`
+    // is the deadline all request issuance blocks on while a backoff is in
+    // effect.
+    let effective_permits = Arc::new(AtomicUsize::new(n_concurrent));
+    let semaphore = Arc::new(Semaphore::new(n_concurrent));
+    let backoff_ms = Arc::new(AtomicUsize::new(0));
+    let consecutive_clean = Arc::new(AtomicUsize::new(0));
+    let resume_at = Arc::new(Mutex::new(Instant::now()));
+
     {
         let found_per_minute = found_per_minute.clone();
         let request_per_second = request_per_second.clone();
 
         let total_requests = total_requests.clone();
         let total_found = total_found.clone();
+        let effective_permits = effective_permits.clone();
 
         thread::spawn(move || {
             print_statistics(
@@ -265,10 +715,17 @@ fn main() {
                 request_per_second,
                 total_requests,
                 total_found,
+                effective_permits,
             )
         });
     }
 
+    let images = Arc::new(Mutex::new(UriGenerator::new(
+        BASE_URL.to_string(),
+        extensions,
+        code_length,
+    )));
+
     println!("Starting with {} concurrent requests.", n_concurrent);
 
     loop {
@@ -278,36 +735,112 @@ fn main() {
         let total_requests = total_requests.clone();
         let total_found = total_found.clone();
 
-        let tx = tx.clone();
-        let tx_hook = tx_hook.clone();
-        let tx_tg = tx_tg.clone();
-        let tx_size = tx_size.clone();
+        let tx_state = tx_state.clone();
+        let known_fingerprints = known_fingerprints.clone();
+        let found_codes = found_codes.clone();
+        let output_sinks = output_sinks.clone();
+
+        let effective_permits = effective_permits.clone();
+        let semaphore = semaphore.clone();
+        let backoff_ms = backoff_ms.clone();
+        let consecutive_clean = consecutive_clean.clone();
+        let resume_at = resume_at.clone();
 
         let https = HttpsConnector::new(4).expect("TLS initialization failed");
         let client = Client::builder().build::<_, hyper::Body>(https);
+        let verify_client = client.clone();
 
-        let images = UriGenerator::new(BASE_URL.to_string(), ".png".to_string());
+        let images = SharedUriGenerator(images.clone());
 
         let user_agent = user_agent.clone();
 
+        let resume_at_issue = resume_at.clone();
+        let semaphore_issue = semaphore.clone();
+
         let work = stream::iter_ok(images)
             .map(move |uri| {
                 let mut request = Request::head(uri.clone());
 
                 request.header("User-Agent", user_agent.clone());
 
-                client
-                    .request(request.body(Body::empty()).unwrap())
-                    .map(move |res| (res, uri))
+                let client = client.clone();
+                let resume_at_issue = resume_at_issue.clone();
+
+                // Acquiring a permit is what actually bounds concurrency: the
+                // semaphore's capacity is kept in lockstep with
+                // `effective_permits`, so a reduced permit count means fewer
+                // requests in flight, not just a delay before issuing them.
+                AcquirePermit::new(semaphore_issue.clone()).and_then(move |(semaphore, mut permit)| {
+                    let deadline = *resume_at_issue.lock().unwrap();
+
+                    // A `Delay` suspends this future without blocking the
+                    // tokio worker thread, unlike `thread::sleep`, so a 429
+                    // cooldown doesn't stall unrelated in-flight requests.
+                    Delay::new(deadline).then(move |_| {
+                        client
+                            .request(request.body(Body::empty()).unwrap())
+                            .then(move |result| {
+                                permit.release(&semaphore);
+                                result.map(move |res| (res, uri))
+                            })
+                    })
+                })
             })
             .buffer_unordered(n_concurrent)
             .and_then(move |(res, uri)| {
                 request_per_second.fetch_add(1, Ordering::SeqCst);
                 total_requests.fetch_add(1, Ordering::SeqCst);
 
+                if res.status() == StatusCode::TOO_MANY_REQUESTS {
+                    consecutive_clean.store(0, Ordering::SeqCst);
+
+                    let backoff = res
+                        .headers()
+                        .get(header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .map(Duration::from_secs)
+                        .unwrap_or_else(|| {
+                            let next = (backoff_ms.load(Ordering::SeqCst) * 2)
+                                .clamp(INITIAL_BACKOFF_MS, MAX_BACKOFF_MS);
+                            backoff_ms.store(next, Ordering::SeqCst);
+                            Duration::from_millis(next as u64)
+                        });
+
+                    *resume_at.lock().unwrap() = Instant::now() + backoff;
+
+                    let current = effective_permits.load(Ordering::SeqCst);
+                    let reduced = (current / 2).max(1);
+                    effective_permits.store(reduced, Ordering::SeqCst);
+                    shrink_semaphore(semaphore.clone(), current - reduced);
+
+                    return Either::A(res.into_body().concat2());
+                }
+
+                if consecutive_clean.fetch_add(1, Ordering::SeqCst) + 1 >= CLEAN_RAMP_WINDOW {
+                    consecutive_clean.store(0, Ordering::SeqCst);
+
+                    let current = effective_permits.load(Ordering::SeqCst);
+                    let raised = (current + 1).min(n_concurrent);
+                    effective_permits.store(raised, Ordering::SeqCst);
+                    semaphore.add_permits(raised - current);
+
+                    let relaxed = backoff_ms.load(Ordering::SeqCst) / 2;
+                    backoff_ms.store(relaxed, Ordering::SeqCst);
+                }
+
                 if res.status() == StatusCode::OK {
-                    found_per_minute.fetch_add(1, Ordering::SeqCst);
-                    total_found.fetch_add(1, Ordering::SeqCst);
+                    let content_length = res
+                        .headers()
+                        .get(header::CONTENT_LENGTH)
+                        .and_then(|size| size.to_str().ok())
+                        .and_then(|size| size.parse::<u64>().ok());
+
+                    // Imgur serves a small placeholder image instead of a 404
+                    // for removed/nonexistent codes; reject it by size alone.
+                    if content_length.map_or(false, |size| size <= min_size) {
+                        return Either::A(res.into_body().concat2());
+                    }
 
                     let image_url = format!(
                         "{}://{}{}",
@@ -316,21 +849,71 @@ fn main() {
                         uri.path_and_query().unwrap()
                     );
 
+                    if verify_placeholder {
+                        let known_fingerprints = known_fingerprints.clone();
+                        let found_codes = found_codes.clone();
+                        let tx_state = tx_state.clone();
+                        let found_per_minute = found_per_minute.clone();
+                        let total_found = total_found.clone();
+                        let output_sinks = output_sinks.clone();
+
+                        return Either::B(verify_client.get(uri).and_then(move |get_res| {
+                            get_res.into_body().concat2().map(move |chunk| {
+                                let is_placeholder =
+                                    known_fingerprints.lock().unwrap().contains(&fingerprint(&chunk));
+
+                                let code = code_from_url(&image_url);
+                                let is_new = !is_placeholder
+                                    && found_codes.lock().unwrap().insert(code.clone());
+
+                                if is_new {
+                                    tx_state.send(code.clone()).is_err();
+
+                                    found_per_minute.fetch_add(1, Ordering::SeqCst);
+                                    total_found.fetch_add(1, Ordering::SeqCst);
+
+                                    println!("{}found valid image at {}", "\x1B[K", image_url);
+
+                                    let found = FoundImage {
+                                        code,
+                                        url: image_url.clone(),
+                                        size: content_length,
+                                    };
+
+                                    for sink in output_sinks.iter() {
+                                        sink.deliver(&found);
+                                    }
+                                }
+
+                                chunk
+                            })
+                        }));
+                    }
+
+                    let code = code_from_url(&image_url);
+
+                    if !found_codes.lock().unwrap().insert(code.clone()) {
+                        return Either::A(res.into_body().concat2());
+                    }
+
+                    tx_state.send(code.clone()).is_err();
+
+                    found_per_minute.fetch_add(1, Ordering::SeqCst);
+                    total_found.fetch_add(1, Ordering::SeqCst);
+
                     println!("{}found valid image at {}", "\x1B[K", image_url);
 
-                    if let Some(size) = res.headers().get(header::CONTENT_LENGTH) {
-                        if let Ok(size) = size.to_str() {
-                            if let Ok(size) = size.parse::<u64>() {
-                                tx_size.send(size).is_err();
-                            }
-                        }
+                    let found = FoundImage {
+                        code,
+                        url: image_url.clone(),
+                        size: content_length,
+                    };
+
+                    for sink in output_sinks.iter() {
+                        sink.deliver(&found);
                     }
-                    
-                    tx.send(image_url.clone()).is_err();
-                    tx_hook.send(image_url.clone()).is_err();
-                    tx_tg.send(image_url.clone()).is_err();
                 }
-                res.into_body().concat2()
+                Either::A(res.into_body().concat2())
             })
             .for_each(|_body| Ok(()))
             .map_err(|e| {
@@ -0,0 +1,332 @@
+use futures::Future;
+use futures::Stream;
+use hyper::{Body, Client, Request};
+use hyper_tls::HttpsConnector;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::fs::OpenOptions;
+use std::io::prelude::*;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+
+/// A single confirmed image, handed to every configured `OutputSink`.
+#[derive(Clone)]
+pub struct FoundImage {
+    pub code: String,
+    pub url: String,
+    pub size: Option<u64>,
+}
+
+/// A destination that found images are delivered to. Each implementation
+/// owns a background thread and a channel, so `deliver` never blocks the
+/// scan loop.
+pub trait OutputSink: Send + Sync {
+    fn deliver(&self, found: &FoundImage);
+}
+
+pub struct TelegramSink {
+    tx: mpsc::Sender<FoundImage>,
+}
+
+impl TelegramSink {
+    pub fn new(channel: String, token: String) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || Self::run(channel, token, rx));
+        Self { tx }
+    }
+
+    fn run(channel: String, token: String, rx: mpsc::Receiver<FoundImage>) {
+        let https = HttpsConnector::new(4).expect("TLS initialization failed");
+        let client = Client::builder().build::<_, hyper::Body>(https);
+
+        let mut runtime = tokio::runtime::Runtime::new().expect("Unable to create a runtime");
+
+        for found in rx {
+            let work = client
+                .get(
+                    format!(
+                        "https://api.telegram.org/bot{}/sendPhoto?chat_id={}&photo={}",
+                        token, channel, found.url
+                    )
+                        .parse()
+                        .unwrap(),
+                )
+                .and_then(|res| res.into_body().concat2())
+                .map(|_body| {})
+                .map_err(|_| {});
+
+            let _ = runtime.block_on(work);
+        }
+    }
+}
+
+impl OutputSink for TelegramSink {
+    fn deliver(&self, found: &FoundImage) {
+        let _ = self.tx.send(found.clone());
+    }
+}
+
+pub struct DiscordSink {
+    tx: mpsc::Sender<FoundImage>,
+}
+
+impl DiscordSink {
+    pub fn new(id: u64, token: String) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || Self::run(id, token, rx));
+        Self { tx }
+    }
+
+    fn run(id: u64, token: String, rx: mpsc::Receiver<FoundImage>) {
+        use serenity::http::Http;
+        use serenity::model::channel::Embed;
+
+        let http = Http::default();
+        let webhook = http.get_webhook_with_token(id, &token).expect("valid webhook");
+
+        for found in rx {
+            let resources = Embed::fake(|e| e.image(found.url));
+            let _ = webhook.execute(&http, false, |w| w.embeds(vec![resources]));
+        }
+    }
+}
+
+impl OutputSink for DiscordSink {
+    fn deliver(&self, found: &FoundImage) {
+        let _ = self.tx.send(found.clone());
+    }
+}
+
+pub struct FileSink {
+    tx: mpsc::Sender<FoundImage>,
+}
+
+impl FileSink {
+    pub fn new(path: String, report_size: bool) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || Self::run(path, report_size, rx));
+        Self { tx }
+    }
+
+    fn run(path: String, report_size: bool, rx: mpsc::Receiver<FoundImage>) {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+
+        for found in rx {
+            if report_size {
+                if let Some(size) = found.size {
+                    file.write_all(format!("{} {}\n", found.url, size).as_bytes())
+                        .unwrap();
+                    continue;
+                }
+            }
+
+            file.write_all(format!("{}\n", found.url).as_bytes())
+                .unwrap();
+        }
+    }
+}
+
+impl OutputSink for FileSink {
+    fn deliver(&self, found: &FoundImage) {
+        let _ = self.tx.send(found.clone());
+    }
+}
+
+pub struct DiskSink {
+    tx: mpsc::Sender<FoundImage>,
+}
+
+impl DiskSink {
+    pub fn new(dir: String, n_concurrent: usize) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || Self::run(dir, n_concurrent, rx));
+        Self { tx }
+    }
+
+    fn run(dir: String, n_concurrent: usize, rx: mpsc::Receiver<FoundImage>) {
+        std::fs::create_dir_all(&dir).expect("unable to create download directory");
+
+        let https = HttpsConnector::new(4).expect("TLS initialization failed");
+        let client = Client::builder().build::<_, hyper::Body>(https);
+
+        let mut runtime = tokio::runtime::Runtime::new().expect("Unable to create a runtime");
+
+        let multi_progress = MultiProgress::new();
+
+        let aggregate = multi_progress.add(ProgressBar::new_spinner());
+        aggregate.set_style(
+            ProgressStyle::default_spinner().template("{spinner} {pos} images downloaded"),
+        );
+        aggregate.enable_steady_tick(100);
+
+        thread::spawn(move || {
+            multi_progress.join().unwrap();
+        });
+
+        let work = futures::stream::iter_ok(rx.into_iter())
+            .map(move |found| {
+                let client = client.clone();
+                let aggregate = aggregate.clone();
+                let dir = dir.clone();
+
+                let file_name = found
+                    .url
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or("unknown")
+                    .to_string();
+                let path = Path::new(&dir).join(&file_name);
+
+                let bar = ProgressBar::new(0);
+                bar.set_style(
+                    ProgressStyle::default_bar()
+                        .template("{msg} [{bar:30}] {bytes}/{total_bytes}")
+                        .progress_chars("=> "),
+                );
+                bar.set_message(&file_name);
+
+                let uri: hyper::Uri = found.url.parse().unwrap();
+
+                client
+                    .get(uri)
+                    .map_err(|_| ())
+                    .and_then(move |res| {
+                        if let Some(len) = res.headers().get(hyper::header::CONTENT_LENGTH) {
+                            if let Ok(len) = len.to_str().unwrap_or("").parse::<u64>() {
+                                bar.set_length(len);
+                            }
+                        }
+
+                        tokio::fs::File::create(path)
+                            .map_err(|_| ())
+                            .and_then(move |file| {
+                                res.into_body()
+                                    .map_err(|_| ())
+                                    .fold(file, move |file, chunk| {
+                                        bar.inc(chunk.len() as u64);
+                                        tokio::io::write_all(file, chunk)
+                                            .map(|(file, _)| file)
+                                            .map_err(|_| ())
+                                    })
+                                    .map(|_| ())
+                            })
+                    })
+                    .then(move |res| {
+                        if res.is_ok() {
+                            aggregate.inc(1);
+                        }
+                        res
+                    })
+            })
+            .buffer_unordered(n_concurrent)
+            .for_each(|_| Ok(()));
+
+        let _ = runtime.block_on(work);
+    }
+}
+
+impl OutputSink for DiskSink {
+    fn deliver(&self, found: &FoundImage) {
+        let _ = self.tx.send(found.clone());
+    }
+}
+
+pub struct JsonWebhookSink {
+    tx: mpsc::Sender<FoundImage>,
+}
+
+impl JsonWebhookSink {
+    pub fn new(endpoint: String) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || Self::run(endpoint, rx));
+        Self { tx }
+    }
+
+    fn run(endpoint: String, rx: mpsc::Receiver<FoundImage>) {
+        let https = HttpsConnector::new(4).expect("TLS initialization failed");
+        let client = Client::builder().build::<_, hyper::Body>(https);
+
+        let mut runtime = tokio::runtime::Runtime::new().expect("Unable to create a runtime");
+
+        for found in rx {
+            let body = serde_json::json!({
+                "code": found.code,
+                "url": found.url,
+                "size": found.size,
+            })
+            .to_string();
+
+            let request = Request::post(&endpoint)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap();
+
+            let work = client
+                .request(request)
+                .and_then(|res| res.into_body().concat2())
+                .map(|_body| {})
+                .map_err(|_| {});
+
+            let _ = runtime.block_on(work);
+        }
+    }
+}
+
+impl OutputSink for JsonWebhookSink {
+    fn deliver(&self, found: &FoundImage) {
+        let _ = self.tx.send(found.clone());
+    }
+}
+
+pub struct IrcSink {
+    tx: mpsc::Sender<FoundImage>,
+}
+
+impl IrcSink {
+    pub fn new(server: String, port: u16, channel: String, nickname: String) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || Self::run(server, port, channel, nickname, rx));
+        Self { tx }
+    }
+
+    fn run(server: String, port: u16, channel: String, nickname: String, rx: mpsc::Receiver<FoundImage>) {
+        use irc::client::prelude::*;
+
+        let config = Config {
+            nickname: Some(nickname),
+            server: Some(server),
+            port: Some(port),
+            channels: Some(vec![channel.clone()]),
+            use_ssl: Some(true),
+            ..Config::default()
+        };
+
+        let client = match IrcClient::from_config(config) {
+            Ok(client) => client,
+            Err(_) => return,
+        };
+
+        if client.identify().is_err() {
+            return;
+        }
+
+        let reader = client.clone();
+        thread::spawn(move || {
+            let _ = reader.for_each_incoming(|_| ());
+        });
+
+        for found in rx {
+            let _ = client.send_privmsg(&channel, &found.url);
+        }
+    }
+}
+
+impl OutputSink for IrcSink {
+    fn deliver(&self, found: &FoundImage) {
+        let _ = self.tx.send(found.clone());
+    }
+}